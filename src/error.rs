@@ -0,0 +1,119 @@
+use crate::ingest::IngestError;
+use crate::store::StorageError;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use serde_json::json;
+
+/// Error uniforme para todos los handlers: cada variante sabe a qué código HTTP y a qué
+/// `type` del sobre de respuesta ("Failure" para errores de cliente, "Fatal" para errores
+/// internos) se traduce, así los handlers devuelven `Result<HttpResponse, AppError>` y usan
+/// `?` en vez del match/eprintln! repetido en cada punto de fallo.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    BadRequest(String),
+    Db(String),
+    Io(String),
+    Storage(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::NotFound(msg) => write!(f, "{}", msg),
+            AppError::BadRequest(msg) => write!(f, "{}", msg),
+            AppError::Db(msg) => write!(f, "database error: {}", msg),
+            AppError::Io(msg) => write!(f, "io error: {}", msg),
+            AppError::Storage(msg) => write!(f, "storage error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound("record not found".to_string())
+            }
+            e => AppError::Db(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::NotFound => AppError::NotFound("object not found".to_string()),
+            StorageError::Io(e) => AppError::Io(e.to_string()),
+            StorageError::Backend(msg) => AppError::Storage(msg),
+        }
+    }
+}
+
+impl From<IngestError> for AppError {
+    fn from(e: IngestError) -> Self {
+        match e {
+            IngestError::TooLarge => {
+                AppError::BadRequest("file exceeds the maximum upload size".to_string())
+            }
+            IngestError::UnsupportedFormat => {
+                AppError::BadRequest("unsupported audio format".to_string())
+            }
+            other => AppError::Io(other.to_string()),
+        }
+    }
+}
+
+/// `"type"` del sobre de respuesta: errores de cliente son "Failure", errores internos
+/// ("no deberían pasar") son "Fatal".
+fn envelope_type(e: &AppError) -> &'static str {
+    match e {
+        AppError::NotFound(_) | AppError::BadRequest(_) => "Failure",
+        AppError::Db(_) | AppError::Io(_) | AppError::Storage(_) => "Fatal",
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Db(_) | AppError::Io(_) | AppError::Storage(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // Los errores "Fatal" son internos (SQL, filesystem, backend de storage); su detalle
+        // solo va al log del servidor, nunca al cliente, para no filtrar rutas, credenciales
+        // o mensajes de los backends en la respuesta HTTP.
+        let content = if envelope_type(self) == "Fatal" {
+            eprintln!("{}", self);
+            "internal server error".to_string()
+        } else {
+            self.to_string()
+        };
+        HttpResponse::build(self.status_code()).json(json!({
+            "type": envelope_type(self),
+            "content": content,
+        }))
+    }
+}
+
+/// Envuelve una respuesta exitosa en el mismo contrato `{ "type", "content" }` que
+/// `AppError::error_response`, para que el frontend siempre reciba la misma forma.
+pub fn ok<T: Serialize>(content: T) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "type": "Success",
+        "content": content,
+    }))
+}
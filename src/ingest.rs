@@ -0,0 +1,300 @@
+use crate::store::ByteStream;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use uuid::Uuid;
+
+// Contenedores/mimetypes que aceptamos en `/upload`; cualquier otra cosa se rechaza antes
+// de gastar tiempo de ffprobe/ffmpeg en ella.
+const SUPPORTED_CONTENT_TYPES: &[&str] = &[
+    "audio/mpeg",
+    "audio/wav",
+    "audio/x-wav",
+    "audio/x-flac",
+    "audio/ogg",
+    "audio/m4a",
+];
+
+// Formato al que normalizamos toda subida aceptada, sea cual sea el original.
+const NORMALIZED_CODEC: &str = "mp3";
+const NORMALIZED_CONTENT_TYPE: &str = "audio/mpeg";
+
+// Resolución del waveform precalculado: cuántos picos guardamos y a qué sample rate
+// decodificamos el PCM mono del que salen (no necesitamos más para dibujar un waveform).
+const WAVEFORM_BUCKETS: usize = 1000;
+const WAVEFORM_SAMPLE_RATE: u32 = 8000;
+
+#[derive(Debug)]
+pub enum IngestError {
+    TooLarge,
+    UnsupportedFormat,
+    Probe(String),
+    Transcode(String),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for IngestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestError::TooLarge => write!(f, "file exceeds the maximum upload size"),
+            IngestError::UnsupportedFormat => write!(f, "unsupported audio format"),
+            IngestError::Probe(msg) => write!(f, "failed to probe audio: {}", msg),
+            IngestError::Transcode(msg) => write!(f, "failed to transcode audio: {}", msg),
+            IngestError::Io(e) => write!(f, "ingest io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+impl From<std::io::Error> for IngestError {
+    fn from(e: std::io::Error) -> Self {
+        IngestError::Io(e)
+    }
+}
+
+/// Metadatos probados y ruta del archivo ya normalizado, listo para `Store::save`.
+pub struct IngestedTrack {
+    pub path: PathBuf,
+    pub duration_secs: f64,
+    pub codec: String,
+    pub content_type: String,
+    /// Picos de amplitud normalizados (0.0-1.0) en hasta `WAVEFORM_BUCKETS` cubos (menos si
+    /// el audio tiene menos muestras), para que el
+    /// frontend dibuje el waveform sin decodificar el audio en el cliente.
+    pub waveform: Vec<f32>,
+}
+
+/// Vuelca `stream` a un archivo temporal (cortando si supera `max_bytes`), sniffa el
+/// contenedor real para rechazar lo que no sea audio soportado, prueba la duración con
+/// `ffprobe` y normaliza el resultado a mp3 con `ffmpeg`.
+pub async fn ingest(mut stream: ByteStream, max_bytes: u64) -> Result<IngestedTrack, IngestError> {
+    let raw_path = std::env::temp_dir().join(format!("democloud-upload-{}.raw", Uuid::new_v4()));
+    let mut raw_file = File::create(&raw_path).await?;
+    let mut written: u64 = 0;
+    let mut sniff_buf: Vec<u8> = Vec::with_capacity(4096);
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        written += chunk.len() as u64;
+        if written > max_bytes {
+            drop(raw_file);
+            let _ = tokio::fs::remove_file(&raw_path).await;
+            return Err(IngestError::TooLarge);
+        }
+        if sniff_buf.len() < sniff_buf.capacity() {
+            let take = (sniff_buf.capacity() - sniff_buf.len()).min(chunk.len());
+            sniff_buf.extend_from_slice(&chunk[..take]);
+        }
+        raw_file.write_all(&chunk).await?;
+    }
+    raw_file.flush().await?;
+    drop(raw_file);
+
+    if let Err(e) = sniff_supported_audio(&sniff_buf) {
+        let _ = tokio::fs::remove_file(&raw_path).await;
+        return Err(e);
+    }
+
+    let probe_result = match probe_duration(&raw_path).await {
+        Ok(probe_result) => probe_result,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&raw_path).await;
+            return Err(e);
+        }
+    };
+
+    let waveform = match waveform_peaks(&raw_path).await {
+        Ok(waveform) => waveform,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&raw_path).await;
+            return Err(e);
+        }
+    };
+
+    let normalized_path =
+        std::env::temp_dir().join(format!("democloud-upload-{}.mp3", Uuid::new_v4()));
+    if let Err(e) = transcode(&raw_path, &normalized_path).await {
+        let _ = tokio::fs::remove_file(&raw_path).await;
+        return Err(e);
+    }
+    let _ = tokio::fs::remove_file(&raw_path).await;
+
+    Ok(IngestedTrack {
+        path: normalized_path,
+        duration_secs: probe_result.duration_secs,
+        codec: NORMALIZED_CODEC.to_string(),
+        content_type: NORMALIZED_CONTENT_TYPE.to_string(),
+        waveform,
+    })
+}
+
+fn sniff_supported_audio(head: &[u8]) -> Result<(), IngestError> {
+    match infer::get(head).map(|kind| kind.mime_type()) {
+        Some(mime) if SUPPORTED_CONTENT_TYPES.contains(&mime) => Ok(()),
+        _ => Err(IngestError::UnsupportedFormat),
+    }
+}
+
+struct ProbeResult {
+    duration_secs: f64,
+}
+
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    format: FfprobeFormat,
+    streams: Vec<FfprobeStream>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFormat {
+    duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    codec_type: String,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+}
+
+async fn probe_duration(path: &Path) -> Result<ProbeResult, IngestError> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| IngestError::Probe(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(IngestError::Probe("ffprobe exited with an error".to_string()));
+    }
+
+    let parsed: FfprobeOutput =
+        serde_json::from_slice(&output.stdout).map_err(|e| IngestError::Probe(e.to_string()))?;
+
+    let audio_stream = parsed
+        .streams
+        .iter()
+        .find(|s| s.codec_type == "audio")
+        .ok_or_else(|| IngestError::Probe("no audio stream found".to_string()))?;
+
+    let sample_rate = audio_stream
+        .sample_rate
+        .as_deref()
+        .and_then(|sr| sr.parse::<u32>().ok())
+        .unwrap_or(0);
+    if sample_rate == 0 {
+        return Err(IngestError::Probe(
+            "audio stream has no valid sample rate".to_string(),
+        ));
+    }
+
+    let channels = audio_stream.channels.unwrap_or(0);
+    if channels == 0 {
+        return Err(IngestError::Probe(
+            "audio stream has no channels".to_string(),
+        ));
+    }
+
+    let duration_secs = parsed
+        .format
+        .duration
+        .as_deref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    Ok(ProbeResult { duration_secs })
+}
+
+// Decodifica `path` a PCM mono de 16 bits vía ffmpeg y lo reduce a `WAVEFORM_BUCKETS` picos
+// de amplitud normalizados a 0.0-1.0, para dibujar un waveform sin decodificar en el cliente.
+async fn waveform_peaks(path: &Path) -> Result<Vec<f32>, IngestError> {
+    let output = Command::new("ffmpeg")
+        .arg("-i")
+        .arg(path)
+        .args([
+            "-vn",
+            "-f",
+            "s16le",
+            "-ac",
+            "1",
+            "-ar",
+            &WAVEFORM_SAMPLE_RATE.to_string(),
+            "pipe:1",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| IngestError::Probe(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(IngestError::Probe(
+            "ffmpeg exited with an error while decoding PCM for the waveform".to_string(),
+        ));
+    }
+
+    let samples: Vec<i16> = output
+        .stdout
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+
+    Ok(compute_peaks(&samples, WAVEFORM_BUCKETS))
+}
+
+// Para cada uno de los `buckets` tramos en los que se divide `samples`, guarda el pico de
+// amplitud absoluta normalizado a 0.0-1.0. Archivos más cortos que `buckets` muestras
+// producen menos cubos en vez de dividir por cero.
+fn compute_peaks(samples: &[i16], buckets: usize) -> Vec<f32> {
+    if samples.is_empty() || buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = buckets.min(samples.len());
+    let n = samples.len();
+
+    // Límites por cubo via i*n/buckets en vez de un chunk_size fijo, para que el resto de
+    // `n / buckets` no se pierda en un último cubo corto (o en que `chunks` genere menos de
+    // `buckets` cubos en total).
+    (0..buckets)
+        .map(|i| {
+            let start = i * n / buckets;
+            let end = (i + 1) * n / buckets;
+            let peak = samples[start..end]
+                .iter()
+                .map(|s| s.unsigned_abs())
+                .max()
+                .unwrap_or(0);
+            // `unsigned_abs(i16::MIN)` es 32768, así que acotamos a 1.0.
+            (peak as f32 / i16::MAX as f32).min(1.0)
+        })
+        .collect()
+}
+
+async fn transcode(input: &Path, output: &Path) -> Result<(), IngestError> {
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(input)
+        .args(["-vn", "-acodec", "libmp3lame", "-b:a", "192k"])
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| IngestError::Transcode(e.to_string()))?;
+
+    if !status.success() {
+        return Err(IngestError::Transcode("ffmpeg exited with an error".to_string()));
+    }
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use actix_cors::Cors;
-use actix_multipart::Multipart;
+use actix_multipart::{Field, Multipart};
 use actix_web::middleware::Logger;
 use actix_web::web::PayloadConfig;
 use actix_web::{
@@ -11,23 +11,57 @@ use rusqlite::{params, Connection};
 use sanitize_filename::sanitize;
 use serde::{Deserialize, Serialize};
 use std::env;
-use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
 use uuid::Uuid;
 
+mod error;
+mod ingest;
+mod store;
+
+use error::{ok, AppError};
+use store::Store;
+
+// Cuánto espera el worker de expiración cuando no hay ningún demo con `expires_at` pendiente.
+const NO_EXPIRY_POLL: Duration = Duration::from_secs(60 * 60);
+
+// Límite del tamaño de subida, tanto para el payload de actix como para el corte a mitad
+// de stream del pipeline de ingest.
+const MAX_UPLOAD_BYTES: u64 = 100 * 1024 * 1024;
+
 fn get_audio_upload_dir() -> PathBuf {
     let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")); // Ruta raíz del proyecto
     dir.push("uploads");
     dir
 }
 
+// Elige el backend de almacenamiento según `STORAGE_BACKEND` ("file" por defecto, o "s3").
+async fn build_store() -> Arc<dyn Store> {
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => {
+            let endpoint =
+                env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set for the s3 storage backend");
+            let region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+            let bucket =
+                env::var("S3_BUCKET").expect("S3_BUCKET must be set for the s3 storage backend");
+            Arc::new(store::ObjectStore::new(&endpoint, &region, &bucket).await)
+        }
+        _ => Arc::new(store::FileStore::new(get_audio_upload_dir())),
+    }
+}
+
 #[derive(Serialize)]
 struct UploadResponse {
     message: String,
     demo_id: String,  // Devolvemos el demo_id para generar la URL
     file_url: String, // También devolvemos el file_url para la previsualización
+    duration: f64,
+    codec: String,
+    content_type: String,
 }
 
 // Estructura para recibir los metadatos
@@ -44,6 +78,9 @@ struct Track {
     title: String,
     file_url: String,
     demo_id: String,
+    duration: f64,
+    codec: String,
+    content_type: String,
 }
 
 // Inicialización de la base de datos y creación de la tabla
@@ -56,7 +93,12 @@ fn init_db() -> Connection {
             title TEXT NOT NULL,
             file_path TEXT NOT NULL,
             demo_id TEXT NOT NULL,
-            user_id TEXT NOT NULL  -- Nuevo campo para almacenar el id de Auth0
+            user_id TEXT NOT NULL,  -- Nuevo campo para almacenar el id de Auth0
+            expires_at INTEGER,  -- Unix timestamp; NULL significa que el demo no expira
+            duration REAL NOT NULL DEFAULT 0,  -- Segundos, calculados por el ingest pipeline
+            codec TEXT NOT NULL DEFAULT '',
+            content_type TEXT NOT NULL DEFAULT '',
+            waveform TEXT NOT NULL DEFAULT '[]'  -- Picos de amplitud (JSON, Vec<f32>) para /demo/{id}/waveform
         )",
         [],
     )
@@ -65,99 +107,269 @@ fn init_db() -> Connection {
     conn
 }
 
-#[post("/upload")]
-async fn upload(mut payload: Multipart, db: web::Data<Mutex<Connection>>) -> impl Responder {
-    let audio_upload_dir = get_audio_upload_dir();
+fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+// Interpreta el campo `keep_for` del formulario de subida: un número de minutos, o "never"
+// (por defecto) para que el demo no expire nunca. Un valor no vacío que no sea "never" ni un
+// entero válido es un error del cliente, no un "never" silencioso.
+fn parse_keep_for(value: &str) -> Result<Option<Duration>, AppError> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("never") {
+        return Ok(None);
+    }
+    let minutes: u64 = value
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("invalid keep_for value: {:?}", value)))?;
+    Ok(Some(Duration::from_secs(minutes * 60)))
+}
+
+fn is_expired(expires_at: Option<i64>) -> bool {
+    match expires_at {
+        Some(expires_at) => expires_at <= now_unix(),
+        None => false,
+    }
+}
+
+// Bloquea el Mutex de la base de datos para un handler, convirtiendo un mutex envenenado en
+// un `AppError::Db` en vez de un panic.
+fn lock_db(db: &Mutex<Connection>) -> Result<std::sync::MutexGuard<'_, Connection>, AppError> {
+    db.lock()
+        .map_err(|_| AppError::Db("database mutex poisoned".to_string()))
+}
+
+// Borra del almacenamiento y de la base de datos todos los tracks cuya expiración ya pasó.
+// El guard del Mutex se libera antes de cada `.await` sobre `store`, que puede ser una
+// llamada de red (ObjectStore).
+async fn sweep_expired(db: &Mutex<Connection>, store: &dyn Store) {
+    let now = now_unix();
 
+    let expired_keys: Vec<String> = {
+        let conn = match db.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("Error locking database during expiry sweep: {:?}", e);
+                return;
+            }
+        };
+        let mut stmt = match conn.prepare(
+            "SELECT file_path FROM tracks WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+        ) {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                eprintln!("Error preparing expiry sweep: {:?}", e);
+                return;
+            }
+        };
+        // Ligamos el resultado del `match` a una variable antes de que termine el bloque:
+        // si usamos el `match` directamente como expresión de cola, el borrow de `stmt`
+        // (dentro de `rows`) se extiende y `conn`/`stmt` "no viven lo suficiente".
+        let keys: Vec<String> = match stmt.query_map(params![now], |row| row.get(0)) {
+            Ok(rows) => rows.filter_map(Result::ok).collect(),
+            Err(e) => {
+                eprintln!("Error querying expired tracks: {:?}", e);
+                return;
+            }
+        };
+        keys
+    };
+
+    if expired_keys.is_empty() {
+        return;
+    }
+
+    for key in &expired_keys {
+        if let Err(e) = store.delete(key).await {
+            eprintln!("Error deleting expired object {:?}: {:?}", key, e);
+        }
+    }
+
+    match db.lock() {
+        Ok(conn) => {
+            if let Err(e) = conn.execute(
+                "DELETE FROM tracks WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now],
+            ) {
+                eprintln!("Error deleting expired tracks from database: {:?}", e);
+            }
+        }
+        Err(e) => eprintln!("Error locking database during expiry sweep: {:?}", e),
+    }
+}
+
+// Tarea en segundo plano que borra los demos expirados cuando llega su `expires_at`. `upload`
+// avisa por `rx` cada vez que programa una expiración, para que el worker recalcule cuánto
+// debe dormir en vez de esperar a su próximo ciclo.
+async fn run_expiry_worker(
+    db: web::Data<Mutex<Connection>>,
+    store: web::Data<Arc<dyn Store>>,
+    mut rx: mpsc::Receiver<()>,
+) {
+    loop {
+        sweep_expired(db.get_ref(), store.get_ref().as_ref()).await;
+
+        let next_expiry = match db.lock() {
+            Ok(conn) => conn
+                .query_row(
+                    "SELECT MIN(expires_at) FROM tracks WHERE expires_at IS NOT NULL",
+                    [],
+                    |row| row.get::<_, Option<i64>>(0),
+                )
+                .ok()
+                .flatten(),
+            Err(e) => {
+                eprintln!("Error locking database in expiry worker: {:?}", e);
+                None
+            }
+        };
+
+        let sleep_duration = match next_expiry {
+            Some(expires_at) => Duration::from_secs((expires_at - now_unix()).max(0) as u64),
+            None => NO_EXPIRY_POLL,
+        };
+
+        // Si `upload` programa una expiración más próxima mientras dormimos, nos despierta antes.
+        let _ = timeout(sleep_duration, rx.recv()).await;
+    }
+}
+
+// Junta los chunks de un campo de texto del formulario multipart y valida que sean UTF-8
+// válido, en vez de asumirlo con `.unwrap()` sobre datos que manda el cliente.
+async fn read_text_field(field: &mut Field) -> Result<String, AppError> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk
+            .map_err(|e| AppError::BadRequest(format!("error reading multipart field: {}", e)))?;
+        buf.extend_from_slice(&chunk);
+    }
+    String::from_utf8(buf)
+        .map_err(|_| AppError::BadRequest("multipart field is not valid UTF-8".to_string()))
+}
+
+// Vuelca el campo "file" del multipart a un archivo temporal. `actix_multipart::Field` nunca
+// es `Send` (guarda un `Rc`/`RefCell` internamente), así que no se puede envolver directamente
+// en el `ByteStream` (`Send`) que esperan `ingest::ingest` y `Store::save`; pasar por disco es
+// el mismo patrón que ya usa `store::stream_local_file` para la salida del ingest pipeline.
+async fn buffer_field_to_file(field: &mut Field) -> Result<PathBuf, AppError> {
+    let path = std::env::temp_dir().join(format!("democloud-incoming-{}.upload", Uuid::new_v4()));
+    let mut file = tokio::fs::File::create(&path).await?;
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk
+            .map_err(|e| AppError::BadRequest(format!("error reading multipart field: {}", e)))?;
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(path)
+}
+
+#[post("/upload")]
+async fn upload(
+    mut payload: Multipart,
+    db: web::Data<Mutex<Connection>>,
+    store: web::Data<Arc<dyn Store>>,
+    expiry_tx: web::Data<mpsc::Sender<()>>,
+) -> Result<HttpResponse, AppError> {
     let mut user_id = String::new(); // Para almacenar el user_id recibido
     let mut artist = String::new(); // Para almacenar el artista
     let mut title = String::new(); // Para almacenar el título
-
-    // Intentamos crear el directorio de subida
-    if let Err(e) = fs::create_dir_all(&audio_upload_dir) {
-        eprintln!("Error creating upload directory: {:?}", e);
-        return HttpResponse::InternalServerError()
-            .body(format!("Failed to create upload directory: {:?}", e));
-    }
+    let mut keep_for = String::new(); // "never" o minutos antes de que expire el demo
 
     // Generar un UUID único para el demo
     let demo_id = Uuid::new_v4(); // Generamos un demo_id único
 
     while let Ok(Some(mut field)) = payload.try_next().await {
-        let content_disposition = field.content_disposition().unwrap();
-        let name = content_disposition.get_name().unwrap();
+        let content_disposition = field.content_disposition().ok_or_else(|| {
+            AppError::BadRequest("multipart field missing Content-Disposition".to_string())
+        })?;
+        let name = content_disposition
+            .get_name()
+            .ok_or_else(|| AppError::BadRequest("multipart field missing a name".to_string()))?
+            .to_string();
 
         // Identificamos los diferentes campos del formulario
         if name == "user_id" {
-            while let Some(chunk) = field.next().await {
-                user_id = String::from_utf8(chunk.unwrap().to_vec()).unwrap();
-            }
+            user_id = read_text_field(&mut field).await?;
         } else if name == "artist" {
-            while let Some(chunk) = field.next().await {
-                artist = String::from_utf8(chunk.unwrap().to_vec()).unwrap();
-            }
+            artist = read_text_field(&mut field).await?;
         } else if name == "title" {
-            while let Some(chunk) = field.next().await {
-                title = String::from_utf8(chunk.unwrap().to_vec()).unwrap();
-            }
+            title = read_text_field(&mut field).await?;
+        } else if name == "keep_for" {
+            keep_for = read_text_field(&mut field).await?;
         } else if name == "file" {
-            // Procesar el archivo
-            let file_extension = "mp3";
-            let filename = format!("{}-{}.{}", sanitize(&title), demo_id, file_extension);
-            let filepath = audio_upload_dir.join(&filename);
+            // Validamos `keep_for` antes de tocar el storage: si es inválido no queremos
+            // haber guardado ya un archivo sin fila en la base de datos que lo referencie.
+            let expires_at = parse_keep_for(&keep_for)?.map(|ttl| now_unix() + ttl.as_secs() as i64);
+
+            // Validar, probar y normalizar el archivo antes de guardarlo
+            let incoming_path = buffer_field_to_file(&mut field).await?;
 
-            // Manejar errores en la creación del archivo
-            let mut f = match web::block(move || std::fs::File::create(filepath.clone())).await {
-                Ok(f) => f,
+            let byte_stream = match store::stream_local_file(incoming_path.clone()).await {
+                Ok(stream) => stream,
                 Err(e) => {
-                    eprintln!("Error creating file: {:?}", e);
-                    return HttpResponse::InternalServerError()
-                        .body(format!("Failed to create file: {:?}", e));
+                    let _ = tokio::fs::remove_file(&incoming_path).await;
+                    return Err(e.into());
                 }
             };
 
-            while let Some(chunk) = field.next().await {
-                let data = match chunk {
-                    Ok(data) => data,
-                    Err(e) => {
-                        eprintln!("Error reading chunk: {:?}", e);
-                        return HttpResponse::InternalServerError()
-                            .body(format!("Error reading file chunk: {:?}", e));
-                    }
-                };
-
-                // Manejar errores al escribir en el archivo
-                f = match web::block(move || {
-                    let mut file = f.unwrap();
-                    file.write_all(&data).map(|_| file)
-                })
-                .await
-                {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("Error writing file: {:?}", e);
-                        return HttpResponse::InternalServerError()
-                            .body(format!("Error writing file: {:?}", e));
-                    }
-                };
-            }
+            let ingest_result = ingest::ingest(byte_stream, MAX_UPLOAD_BYTES).await;
+            let _ = tokio::fs::remove_file(&incoming_path).await;
+            let ingested = ingest_result?;
 
-            // Insertar los datos en la base de datos con `user_id`
-            let conn = match db.lock() {
-                Ok(conn) => conn,
+            let file_extension = "mp3";
+            let filename = format!("{}-{}.{}", sanitize(&title), demo_id, file_extension);
+
+            let normalized_stream = match store::stream_local_file(ingested.path.clone()).await {
+                Ok(stream) => stream,
                 Err(e) => {
-                    eprintln!("Error locking database: {:?}", e);
-                    return HttpResponse::InternalServerError()
-                        .body(format!("Failed to lock database: {:?}", e));
+                    let _ = tokio::fs::remove_file(&ingested.path).await;
+                    return Err(e.into());
                 }
             };
 
-            if let Err(e) = conn.execute(
-                "INSERT INTO tracks (artist, title, file_path, demo_id, user_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![&artist, &title, &filename, demo_id.to_string(), &user_id],
-            ) {
-                eprintln!("Error inserting track into database: {:?}", e);
-                return HttpResponse::InternalServerError().body(format!("Failed to insert track into database: {:?}", e));
+            let save_result = store.save(&filename, normalized_stream).await;
+            let _ = tokio::fs::remove_file(&ingested.path).await;
+            save_result?;
+
+            let waveform_json = serde_json::to_string(&ingested.waveform)
+                .unwrap_or_else(|_| "[]".to_string());
+
+            // Insertar los datos en la base de datos con `user_id`. Si falla, el archivo que
+            // ya guardamos en `store` quedaría huérfano, así que lo borramos antes de propagar
+            // el error.
+            let insert_result = match lock_db(&db) {
+                Ok(conn) => conn
+                    .execute(
+                        "INSERT INTO tracks (artist, title, file_path, demo_id, user_id, expires_at, duration, codec, content_type, waveform) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            &artist,
+                            &title,
+                            &filename,
+                            demo_id.to_string(),
+                            &user_id,
+                            expires_at,
+                            ingested.duration_secs,
+                            &ingested.codec,
+                            &ingested.content_type,
+                            &waveform_json,
+                        ],
+                    )
+                    .map_err(AppError::from),
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = insert_result {
+                let _ = store.delete(&filename).await;
+                return Err(e);
+            }
+
+            if expires_at.is_some() {
+                // Avisamos al worker de expiración por si esta expiración es más próxima
+                // que la que tenía programada; si el canal está lleno ya se despertará solo.
+                let _ = expiry_tx.try_send(());
             }
 
             // Devolver la URL de la demo pública
@@ -165,13 +377,16 @@ async fn upload(mut payload: Multipart, db: web::Data<Mutex<Connection>>) -> imp
                 message: String::from("File uploaded successfully"),
                 demo_id: demo_id.to_string(),
                 file_url: format!("/audio/{}", filename),
+                duration: ingested.duration_secs,
+                codec: ingested.codec,
+                content_type: ingested.content_type,
             };
 
-            return HttpResponse::Ok().json(response);
+            return Ok(ok(response));
         }
     }
 
-    HttpResponse::BadRequest().body("File upload failed")
+    Err(AppError::BadRequest("File upload failed".to_string()))
 }
 
 // Handler para obtener los tracks
@@ -179,138 +394,190 @@ async fn upload(mut payload: Multipart, db: web::Data<Mutex<Connection>>) -> imp
 async fn get_tracks(
     db: web::Data<Mutex<Connection>>,
     req: actix_web::HttpRequest,
-) -> impl Responder {
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(e) => {
-            eprintln!("Error locking database: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to lock database");
-        }
-    };
+) -> Result<HttpResponse, AppError> {
+    let conn = lock_db(&db)?;
 
     // Obtener el user_id del encabezado o del token JWT decodificado
     let user_id = match req.headers().get("user_id") {
         Some(value) => value.to_str().unwrap_or("").to_string(),
-        None => return HttpResponse::BadRequest().body("Missing user_id in headers"),
+        None => return Err(AppError::BadRequest("Missing user_id in headers".to_string())),
     };
 
-    let mut stmt = match conn
-        .prepare("SELECT artist, title, file_path, demo_id FROM tracks WHERE user_id = ?1")
-    {
-        Ok(stmt) => stmt,
-        Err(e) => {
-            eprintln!("Error preparing SQL statement: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to prepare SQL statement");
-        }
-    };
+    let mut stmt = conn.prepare(
+        "SELECT artist, title, file_path, demo_id, duration, codec, content_type FROM tracks WHERE user_id = ?1",
+    )?;
 
-    let track_iter = match stmt.query_map([&user_id], |row| {
+    let track_iter = stmt.query_map([&user_id], |row| {
         Ok(Track {
             artist: row.get(0)?,
             title: row.get(1)?,
             file_url: format!("/audio/{}", row.get::<_, String>(2)?),
             demo_id: row.get(3)?,
+            duration: row.get(4)?,
+            codec: row.get(5)?,
+            content_type: row.get(6)?,
         })
-    }) {
-        Ok(track_iter) => track_iter,
-        Err(e) => {
-            eprintln!("Error mapping query: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to query tracks");
+    })?;
+
+    let tracks: Vec<Track> = track_iter.collect::<Result<_, _>>()?;
+
+    Ok(ok(tracks))
+}
+
+// Parsea un encabezado `Range: bytes=start-end` en un rango inclusivo (start, end).
+// Devuelve Err(()) cuando el rango no se puede satisfacer para un archivo de `file_len` bytes.
+fn parse_byte_range(range_header: &str, file_len: u64) -> Result<(u64, u64), ()> {
+    let spec = range_header.trim().strip_prefix("bytes=").ok_or(())?;
+    let (start_str, end_str) = spec.split_once('-').ok_or(())?;
+
+    let (start, end) = if start_str.is_empty() {
+        // Rango sufijo: los últimos `end_str` bytes del archivo.
+        let suffix_len: u64 = end_str.parse().map_err(|_| ())?;
+        if suffix_len == 0 {
+            return Err(());
         }
+        (
+            file_len.saturating_sub(suffix_len),
+            file_len.saturating_sub(1),
+        )
+    } else {
+        let start: u64 = start_str.parse().map_err(|_| ())?;
+        let end = if end_str.is_empty() {
+            file_len.saturating_sub(1)
+        } else {
+            end_str
+                .parse::<u64>()
+                .map_err(|_| ())?
+                .min(file_len.saturating_sub(1))
+        };
+        (start, end)
     };
 
-    let mut tracks = Vec::new();
-    for track in track_iter {
-        tracks.push(track.unwrap());
+    if file_len == 0 || start > end || start >= file_len {
+        return Err(());
     }
 
-    HttpResponse::Ok().json(tracks)
+    Ok((start, end))
+}
+
+// Sirve la `key` de `store` con soporte de HTTP Range; lo comparten `stream_audio` y
+// `stream_demo`, que antes duplicaban esta lógica contra `std::fs` directamente.
+async fn stream_file(
+    req: &HttpRequest,
+    store: &dyn Store,
+    key: &str,
+    content_type: &'static str,
+) -> Result<HttpResponse, AppError> {
+    let meta = store.metadata(key).await?;
+    let file_len = meta.len;
+
+    let range_header = req
+        .headers()
+        .get(http::header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    let (mut builder, start, len, content_range) = match range_header {
+        Some(range_header) => match parse_byte_range(&range_header, file_len) {
+            Ok((start, end)) => (
+                HttpResponse::PartialContent(),
+                start,
+                end - start + 1,
+                Some(format!("bytes {}-{}/{}", start, end, file_len)),
+            ),
+            Err(()) => {
+                return Ok(HttpResponse::RangeNotSatisfiable()
+                    .insert_header((http::header::CONTENT_RANGE, format!("bytes */{}", file_len)))
+                    .finish());
+            }
+        },
+        None => (HttpResponse::Ok(), 0, file_len, None),
+    };
+
+    let stream = store.read_range(key, start, len).await?;
+
+    builder
+        .content_type(content_type)
+        .insert_header((http::header::ACCEPT_RANGES, "bytes"))
+        .insert_header((http::header::CONTENT_LENGTH, len.to_string()));
+
+    if let Some(content_range) = content_range {
+        builder.insert_header((http::header::CONTENT_RANGE, content_range));
+    }
+
+    if let Some(modified) = meta.last_modified {
+        builder.insert_header((
+            http::header::LAST_MODIFIED,
+            http::header::HttpDate::from(modified).to_string(),
+        ));
+        let mtime_secs = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        builder.insert_header((
+            http::header::ETAG,
+            format!("\"{:x}-{:x}\"", file_len, mtime_secs),
+        ));
+    }
+
+    Ok(builder.streaming(stream))
 }
 
 // Handler para servir los archivos de audio
 #[get("/audio/{filename}")]
-async fn stream_audio(path: web::Path<String>) -> impl Responder {
+async fn stream_audio(
+    req: HttpRequest,
+    path: web::Path<String>,
+    store: web::Data<Arc<dyn Store>>,
+) -> Result<HttpResponse, AppError> {
     let filename = path.into_inner();
-    let filepath: PathBuf = get_audio_upload_dir().join(&filename);
-
-    if filepath.exists() {
-        HttpResponse::Ok()
-            .content_type("audio/mpeg")
-            .body(fs::read(filepath).unwrap())
-    } else {
-        HttpResponse::NotFound().body("File not found")
-    }
+    stream_file(&req, store.get_ref().as_ref(), &filename, "audio/mpeg").await
 }
 
 // Handler para eliminar un archivo y su registro en la base de datos
 #[delete("/audio/{filename}")]
-async fn delete_audio(path: web::Path<String>, db: web::Data<Mutex<Connection>>) -> impl Responder {
+async fn delete_audio(
+    path: web::Path<String>,
+    db: web::Data<Mutex<Connection>>,
+    store: web::Data<Arc<dyn Store>>,
+) -> Result<HttpResponse, AppError> {
     let filename = path.into_inner();
-    let filepath: PathBuf = get_audio_upload_dir().join(&filename);
-
-    if filepath.exists() {
-        if let Err(e) = fs::remove_file(&filepath) {
-            eprintln!("Error deleting file: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to delete file");
-        }
 
-        let conn = match db.lock() {
-            Ok(conn) => conn,
-            Err(e) => {
-                eprintln!("Error locking database: {:?}", e);
-                return HttpResponse::InternalServerError().body("Failed to lock database");
-            }
-        };
+    store.delete(&filename).await?;
 
-        if let Err(e) = conn.execute(
-            "DELETE FROM tracks WHERE file_path = ?1",
-            params![&filename],
-        ) {
-            eprintln!("Error deleting track from database: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .body("Failed to delete track from database");
-        }
+    let conn = lock_db(&db)?;
+    conn.execute(
+        "DELETE FROM tracks WHERE file_path = ?1",
+        params![&filename],
+    )?;
 
-        HttpResponse::Ok().body("File and record deleted successfully")
-    } else {
-        HttpResponse::NotFound().body("File not found")
-    }
+    Ok(ok("File and record deleted successfully"))
 }
 
 #[get("/demo/{demo_id}")]
-async fn stream_demo(path: web::Path<String>, db: web::Data<Mutex<Connection>>) -> impl Responder {
+async fn stream_demo(
+    req: HttpRequest,
+    path: web::Path<String>,
+    db: web::Data<Mutex<Connection>>,
+    store: web::Data<Arc<dyn Store>>,
+) -> Result<HttpResponse, AppError> {
     let demo_id = path.into_inner();
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(e) => {
-            eprintln!("Error locking database: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to lock database");
-        }
-    };
 
-    // Buscar el archivo basado en demo_id
-    let mut stmt = match conn.prepare("SELECT file_path FROM tracks WHERE demo_id = ?1") {
-        Ok(stmt) => stmt,
-        Err(e) => {
-            eprintln!("Error preparing SQL statement: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to prepare SQL statement");
-        }
+    // El guard del Mutex se libera al final de este bloque, antes del `.await` de
+    // `stream_file` más abajo.
+    let result = {
+        let conn = lock_db(&db)?;
+        let mut stmt = conn.prepare("SELECT file_path, expires_at FROM tracks WHERE demo_id = ?1")?;
+        stmt.query_row([&demo_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+        })
     };
 
-    let result = stmt.query_row([&demo_id], |row| row.get::<_, String>(0)); // Especificamos que esperamos un String
-
     match result {
-        Ok(file_path) => {
-            let filepath = get_audio_upload_dir().join(file_path);
-            if filepath.exists() {
-                HttpResponse::Ok()
-                    .content_type("audio/mpeg")
-                    .body(fs::read(filepath).unwrap())
-            } else {
-                HttpResponse::NotFound().body("File not found")
-            }
+        Ok((file_path, expires_at)) if !is_expired(expires_at) => {
+            stream_file(&req, store.get_ref().as_ref(), &file_path, "audio/mpeg").await
         }
-        Err(_) => HttpResponse::NotFound().body("Demo not found"),
+        _ => Err(AppError::NotFound("Demo not found".to_string())),
     }
 }
 
@@ -318,29 +585,62 @@ async fn stream_demo(path: web::Path<String>, db: web::Data<Mutex<Connection>>)
 async fn get_demo_details(
     path: web::Path<String>,
     db: web::Data<Mutex<Connection>>,
-) -> impl Responder {
+) -> Result<HttpResponse, AppError> {
     let demo_id = path.into_inner();
-    let conn = db.lock().unwrap();
+    let conn = lock_db(&db)?;
 
     // Buscar el archivo basado en demo_id
-    let mut stmt = conn
-        .prepare("SELECT artist, title, file_path, demo_id FROM tracks WHERE demo_id = ?1")
-        .expect("Failed to prepare statement");
+    let mut stmt = conn.prepare(
+        "SELECT artist, title, file_path, demo_id, expires_at, duration, codec, content_type \
+         FROM tracks WHERE demo_id = ?1",
+    )?;
 
     let result = stmt.query_row([&demo_id], |row| {
-        Ok(Track {
+        let track = Track {
             artist: row.get(0)?,
             title: row.get(1)?,
             file_url: format!("/audio/{}", row.get::<_, String>(2)?),
             demo_id: row.get(3)?, // Incluimos el demo_id aquí
-        })
+            duration: row.get(5)?,
+            codec: row.get(6)?,
+            content_type: row.get(7)?,
+        };
+        let expires_at: Option<i64> = row.get(4)?;
+        Ok((track, expires_at))
     });
 
     match result {
-        Ok(track) => HttpResponse::Ok().json(track),
-        Err(_) => HttpResponse::NotFound().body("Demo not found"),
+        Ok((track, expires_at)) if !is_expired(expires_at) => Ok(ok(track)),
+        _ => Err(AppError::NotFound("Demo not found".to_string())),
+    }
+}
+
+// Handler para el waveform precalculado de una demo, usado por el frontend para dibujarlo
+// sin decodificar el audio en el cliente.
+#[get("/demo/{demo_id}/waveform")]
+async fn get_demo_waveform(
+    path: web::Path<String>,
+    db: web::Data<Mutex<Connection>>,
+) -> Result<HttpResponse, AppError> {
+    let demo_id = path.into_inner();
+    let conn = lock_db(&db)?;
+
+    let result = conn.query_row(
+        "SELECT waveform, expires_at FROM tracks WHERE demo_id = ?1",
+        [&demo_id],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?)),
+    );
+
+    match result {
+        Ok((waveform_json, expires_at)) if !is_expired(expires_at) => {
+            let waveform: Vec<f32> = serde_json::from_str(&waveform_json)
+                .map_err(|e| AppError::Db(format!("corrupt waveform column: {}", e)))?;
+            Ok(ok(waveform))
+        }
+        _ => Err(AppError::NotFound("Demo not found".to_string())),
     }
 }
+
 #[options("/{any:.*}")]
 async fn handle_options(_req: HttpRequest) -> impl Responder {
     HttpResponse::Ok()
@@ -356,7 +656,14 @@ async fn handle_options(_req: HttpRequest) -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    HttpServer::new(|| {
+    let db = web::Data::new(Mutex::new(init_db()));
+    let store: web::Data<Arc<dyn Store>> = web::Data::new(build_store().await);
+    let (expiry_tx, expiry_rx) = mpsc::channel::<()>(16);
+    let expiry_tx = web::Data::new(expiry_tx);
+
+    actix_web::rt::spawn(run_expiry_worker(db.clone(), store.clone(), expiry_rx));
+
+    HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .wrap(
@@ -371,13 +678,17 @@ async fn main() -> std::io::Result<()> {
                     .allow_any_header()
                     .supports_credentials(),
             )
-            .app_data(PayloadConfig::new(100 * 1024 * 1024))
+            .app_data(PayloadConfig::new(MAX_UPLOAD_BYTES as usize))
+            .app_data(db.clone())
+            .app_data(store.clone())
+            .app_data(expiry_tx.clone())
             .service(upload)
             .service(get_tracks)
             .service(delete_audio)
             .service(stream_audio)
             .service(stream_demo)
             .service(get_demo_details)
+            .service(get_demo_waveform)
     })
     .bind(("0.0.0.0", 8080))?
     .run()
@@ -0,0 +1,72 @@
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use futures_util::Stream;
+use std::pin::Pin;
+use std::time::SystemTime;
+
+mod file_store;
+mod object_store;
+
+pub use file_store::FileStore;
+pub use object_store::ObjectStore;
+
+/// Flujo de bytes devuelto por `Store::read_range`, ya adaptado al tipo que espera
+/// `HttpResponse::streaming`.
+pub type ByteStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>;
+
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound,
+    Io(std::io::Error),
+    Backend(String),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "object not found"),
+            StorageError::Io(e) => write!(f, "storage io error: {}", e),
+            StorageError::Backend(msg) => write!(f, "storage backend error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            StorageError::NotFound
+        } else {
+            StorageError::Io(e)
+        }
+    }
+}
+
+/// Tamaño y fecha de modificación de un objeto almacenado; basta para que los handlers
+/// construyan `Content-Length`, `Last-Modified` y `ETag` sin saber qué backend los sirve.
+pub struct ObjectMeta {
+    pub len: u64,
+    pub last_modified: Option<SystemTime>,
+}
+
+/// Desacopla los handlers del disco local: hoy guardamos en `FileStore`, mañana en un
+/// bucket S3-compatible vía `ObjectStore`, sin que `upload`/`stream_audio`/`delete_audio`
+/// cambien. El `file_path` guardado en la base de datos es la `key` de este trait, no una
+/// ruta de archivo.
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn save(&self, key: &str, stream: ByteStream) -> Result<(), StorageError>;
+    async fn metadata(&self, key: &str) -> Result<ObjectMeta, StorageError>;
+    async fn read_range(&self, key: &str, start: u64, len: u64)
+        -> Result<ByteStream, StorageError>;
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Convierte un archivo local (p.ej. la salida del pipeline de ingest) en el mismo tipo de
+/// stream que espera `Store::save`, sin cargarlo entero en memoria.
+pub async fn stream_local_file(path: std::path::PathBuf) -> std::io::Result<ByteStream> {
+    let file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    Ok(file_store::chunked_read_stream(file, len))
+}
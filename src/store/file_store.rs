@@ -0,0 +1,84 @@
+use super::{ByteStream, ObjectMeta, StorageError, Store};
+use actix_web::web::Bytes;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Transmite `len` bytes de `file` a partir de su posición de lectura actual, en trozos, en
+// vez de cargarlo entero en memoria. La comparte `FileStore::read_range` y
+// `super::stream_local_file`.
+pub(super) fn chunked_read_stream(file: fs::File, len: u64) -> ByteStream {
+    let stream = futures_util::stream::unfold((file, len), |(mut file, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        let to_read = remaining.min(CHUNK_SIZE as u64) as usize;
+        let mut buf = vec![0u8; to_read];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(Bytes::from(buf)), (file, remaining - n as u64)))
+            }
+            Err(e) => Some((Err(e), (file, 0))),
+        }
+    });
+
+    Box::pin(stream)
+}
+
+/// `Store` respaldado por el disco local, el backend histórico de este servicio.
+pub struct FileStore {
+    base_dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(base_dir: PathBuf) -> Self {
+        FileStore { base_dir }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.base_dir.join(key)
+    }
+}
+
+#[async_trait]
+impl Store for FileStore {
+    async fn save(&self, key: &str, mut stream: ByteStream) -> Result<(), StorageError> {
+        fs::create_dir_all(&self.base_dir).await?;
+        let mut file = fs::File::create(self.path_for(key)).await?;
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        Ok(())
+    }
+
+    async fn metadata(&self, key: &str) -> Result<ObjectMeta, StorageError> {
+        let metadata = fs::metadata(self.path_for(key)).await?;
+        Ok(ObjectMeta {
+            len: metadata.len(),
+            last_modified: metadata.modified().ok(),
+        })
+    }
+
+    async fn read_range(
+        &self,
+        key: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<ByteStream, StorageError> {
+        let mut file = fs::File::open(self.path_for(key)).await?;
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+
+        Ok(chunked_read_stream(file, len))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        fs::remove_file(self.path_for(key)).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use super::{ByteStream, ObjectMeta, StorageError, Store};
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream as S3ByteStream;
+use aws_sdk_s3::Client;
+use futures_util::StreamExt;
+use tokio_util::io::ReaderStream;
+
+/// `Store` respaldado por un bucket S3-compatible (AWS S3, MinIO, R2, ...), configurado
+/// desde variables de entorno en `build_store` (ver `main.rs`).
+pub struct ObjectStore {
+    client: Client,
+    bucket: String,
+}
+
+impl ObjectStore {
+    pub async fn new(endpoint: &str, region: &str, bucket: &str) -> Self {
+        let config = aws_config::from_env()
+            .region(aws_config::Region::new(region.to_string()))
+            .endpoint_url(endpoint)
+            .load()
+            .await;
+
+        ObjectStore {
+            client: Client::new(&config),
+            bucket: bucket.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Store for ObjectStore {
+    async fn save(&self, key: &str, mut stream: ByteStream) -> Result<(), StorageError> {
+        // La API PutObject necesita el tamaño del cuerpo por adelantado, así que
+        // acumulamos el upload antes de enviarlo. Aquí no hay límite propio: el tamaño del
+        // archivo original lo acota el pipeline de ingest (`MAX_UPLOAD_BYTES`), no `PayloadConfig`,
+        // que no se aplica al extractor `Multipart`.
+        let mut buf = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            buf.extend_from_slice(&chunk?);
+        }
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(S3ByteStream::from(buf))
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn metadata(&self, key: &str) -> Result<ObjectMeta, StorageError> {
+        let head = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        Ok(ObjectMeta {
+            len: head.content_length().unwrap_or(0).max(0) as u64,
+            last_modified: head
+                .last_modified()
+                .and_then(|dt| dt.to_owned().try_into().ok()),
+        })
+    }
+
+    async fn read_range(
+        &self,
+        key: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<ByteStream, StorageError> {
+        let end = start + len.saturating_sub(1);
+
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={}-{}", start, end))
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        // `aws_sdk_s3::primitives::ByteStream` no implementa `futures_util::Stream`, así que
+        // no podemos mapearlo directamente; lo consumimos como `AsyncRead` y lo envolvemos en
+        // un `ReaderStream` para obtener el `Stream<Item = io::Result<Bytes>>` que espera `Store`.
+        let reader = output.body.into_async_read();
+        let stream = ReaderStream::new(reader);
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        Ok(())
+    }
+}